@@ -6,19 +6,24 @@ extern crate env_logger;
 extern crate log;
 extern crate reqwest;
 
+use std::time::Duration;
 use serde::de::DeserializeOwned;
 use reqwest::header::CONTENT_TYPE;
+use tokio::sync::RwLock;
 
 pub mod types;
 use types::BasicAuth;
 use types::{Result, RpcResponse, RpcResponseArgument, RpcRequest, Nothing};
-use types::SessionGet;
+use types::{SessionGet, SessionSet};
 use types::{TorrentGetField, Torrents, Torrent};
 use types::TorrentAction;
+use types::TorrentSetArgs;
 
 pub struct TransClient {
     url: String,
-    auth: Option<BasicAuth>
+    auth: Option<BasicAuth>,
+    session_id: RwLock<Option<String>>,
+    client: reqwest::Client
 }
 
 impl TransClient {
@@ -26,7 +31,9 @@ impl TransClient {
     pub fn with_auth(url: &str, basic_auth: BasicAuth) -> TransClient {
         TransClient {
             url: url.to_string(),
-            auth: Some(basic_auth)
+            auth: Some(basic_auth),
+            session_id: RwLock::new(None),
+            client: reqwest::Client::new()
         }
     }
 
@@ -34,43 +41,76 @@ impl TransClient {
     pub fn new(url: &str) -> TransClient {
         TransClient {
             url: url.to_string(),
-            auth: None
+            auth: None,
+            session_id: RwLock::new(None),
+            client: reqwest::Client::new()
         }
     }
 
+    /// Returns an HTTP(S) client built from a customized `reqwest::Client`,
+    /// letting callers enable gzip response decompression, set a request
+    /// timeout and/or supply a custom user agent. The built client is
+    /// reused (and its connection pool kept warm) across every call made
+    /// through this `TransClient`.
+    ///
+    /// # Errors
+    ///
+    /// Any error building the underlying `reqwest::Client`
+    pub fn with_config(url: &str, auth: Option<BasicAuth>, gzip: bool, timeout: Option<Duration>, user_agent: Option<&str>) -> Result<TransClient> {
+        let mut builder = reqwest::Client::builder().gzip(gzip);
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(user_agent) = user_agent {
+            builder = builder.user_agent(user_agent.to_string());
+        }
+        Ok(TransClient {
+            url: url.to_string(),
+            auth,
+            session_id: RwLock::new(None),
+            client: builder.build()?
+        })
+    }
+
     /// Prepares a request for provided server and auth
     fn rpc_request(&self) -> reqwest::RequestBuilder {
-        let client = reqwest::Client::new();
         if let Some(auth) = &self.auth {
-            client.post(&self.url)
+            self.client.post(&self.url)
             .basic_auth(&auth.user, Some(&auth.password))
         } else {
-            client.post(&self.url)
+            self.client.post(&self.url)
         }
         .header(CONTENT_TYPE, "application/json")
     }
-    
-    /// Performs session-get call and takes the x-transmission-session-id
-    /// header to perform calls, using it's value
-    /// 
+
+    /// Sends the given request, attaching the cached session id if we
+    /// already have one. Transmission only starts requiring it once it
+    /// has told us about it via a 409 response, so the first call of a
+    /// fresh client is sent without the header.
+    async fn send_request(&self, request: &RpcRequest) -> Result<reqwest::Response> {
+        let mut rq = self.rpc_request();
+        if let Some(session_id) = self.session_id.read().await.clone() {
+            rq = rq.header("X-Transmission-Session-Id", session_id);
+        }
+        Ok(rq.json(request).send().await?)
+    }
+
+    /// Reads the `X-Transmission-Session-Id` header off a 409 response
+    /// and caches it for subsequent calls
+    ///
     /// # Errors
-    /// 
-    /// Panics if any IO error happens
-    async fn get_session_id(&self) -> String {
-        info!("Requesting session id info");
-        let response: reqwest::Response = self.rpc_request()
-        .json(&RpcRequest::session_get())
-        .send()
-        .await
-        .unwrap();
-        let session_id = response.headers()
+    ///
+    /// If the response has no session id header, or its value is not
+    /// visible ASCII
+    async fn renew_session_id(&self, response: &reqwest::Response) -> Result<()> {
+        let fresh_id = response.headers()
             .get("x-transmission-session-id")
-            .expect("Unable to get session id")
-            .to_str()
-            .unwrap()
+            .ok_or_else(|| "Transmission answered 409 without a session id header")?
+            .to_str()?
             .to_owned();
-        info!("Received session id: {}", session_id);
-        session_id
+        info!("Received new session id: {}", fresh_id);
+        *self.session_id.write().await = Some(fresh_id);
+        Ok(())
     }
 
     /// Performs a session get call
@@ -86,6 +126,16 @@ impl TransClient {
         self.call(RpcRequest::session_get()).await
     }
 
+    /// Performs a session set call, to change server settings such as
+    /// the default download location or global bandwidth caps
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    pub async fn session_set(&self, args: SessionSet) -> Result<RpcResponse<Nothing>> {
+        self.call(RpcRequest::session_set(args)).await
+    }
+
     /// Performs a torrent get call
     /// 
     /// # Errors
@@ -100,48 +150,86 @@ impl TransClient {
     }
 
     /// Performs a torrent action call
-    /// 
+    ///
     /// # Errors
-    /// 
-    /// Any IO Error or Deserialization error
-    /// 
+    ///
+    /// Any IO Error or Deserialization error. Also errors on an empty
+    /// `ids` list; see [`require_ids`].
+    ///
     /// # Example
-    /// 
+    ///
     /// in examples/torrent-action.rs
     pub async fn torrent_action(&self, action: TorrentAction, ids: Vec<i64>) -> Result<RpcResponse<Nothing>> {
+        require_ids(&ids, "torrent_action")?;
         self.call(RpcRequest::torrent_action(action, ids)).await
     }
 
-    /// Performs an JRPC call to the server
-    /// 
+    /// Performs a torrent remove call
+    ///
     /// # Errors
-    /// 
+    ///
+    /// Any IO Error or Deserialization error. Also errors on an empty
+    /// `ids` list (see [`require_ids`]) -- worth calling out here since
+    /// an accidentally-empty list would otherwise pair with
+    /// `delete_local_data` to wipe every torrent on the server.
+    pub async fn torrent_remove(&self, ids: Vec<i64>, delete_local_data: bool) -> Result<RpcResponse<Nothing>> {
+        require_ids(&ids, "torrent_remove")?;
+        self.call(RpcRequest::torrent_remove(ids, delete_local_data)).await
+    }
+
+    /// Performs a torrent set call, e.g. to mark files wanted/unwanted
+    /// or change their download priority
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error. Also errors on an empty
+    /// `args.ids` list; see [`require_ids`].
+    pub async fn torrent_set(&self, args: TorrentSetArgs) -> Result<RpcResponse<Nothing>> {
+        require_ids(&args.ids, "torrent_set")?;
+        self.call(RpcRequest::torrent_set(args)).await
+    }
+
+    /// Performs an JRPC call to the server, caching the session id and
+    /// transparently retrying once if Transmission rejects it with a
+    /// `409 Conflict` CSRF challenge
+    ///
+    /// # Errors
+    ///
     /// Any IO Error or Deserialization error
     async fn call<RS> (&self, request: RpcRequest) -> Result<RpcResponse<RS>>
     where   RS : RpcResponseArgument + DeserializeOwned + std::fmt::Debug
     {
         info!("Loaded auth: {:?}", &self.auth);
-        let rq: reqwest::RequestBuilder = self.rpc_request()
-            .header("X-Transmission-Session-Id", self.get_session_id().await)
-            .json(&request);
-        info!("Request body: {:?}", rq.try_clone().unwrap().body_string()?);
-        let resp: reqwest::Response = rq.send().await?;
+        let resp = self.send_request(&request).await?;
+        let resp = if resp.status() == reqwest::StatusCode::CONFLICT {
+            info!("Session id rejected (409), renewing and retrying once");
+            self.renew_session_id(&resp).await?;
+            // Drain the 409 body so reqwest can return the connection to its
+            // pool instead of closing the socket on a partially-read response.
+            let _ = resp.bytes().await;
+            let retried = self.send_request(&request).await?;
+            if retried.status() == reqwest::StatusCode::CONFLICT {
+                let _ = retried.bytes().await;
+                return Err("Transmission still rejected the session id after renewal".into());
+            }
+            retried
+        } else {
+            resp
+        };
         let rpc_response: RpcResponse<RS> = resp.json().await?;
         info!("Response body: {:#?}", rpc_response);
         Ok(rpc_response)
     }
 }
 
-trait BodyString {
-    fn body_string(self) -> Result<String>;
-}
-
-impl BodyString for reqwest::RequestBuilder {
-    fn body_string(self) -> Result<String> {
-        let rq = self.build()?;
-        let body = rq.body().unwrap().as_bytes().unwrap();
-        Ok(std::str::from_utf8(body)?.to_string())
+/// Rejects an empty id list for the RPC methods where Transmission
+/// treats a missing/empty id filter as "every torrent on the server",
+/// so an accidentally-empty list can't silently apply to all of them
+fn require_ids(ids: &[i64], method: &str) -> Result<()> {
+    if ids.is_empty() {
+        return Err(format!("{} requires at least one torrent id", method).into());
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -165,3 +253,89 @@ mod tests {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod call_retry_tests {
+    use crate::{TransClient, RpcRequest, Nothing};
+    use mockito::Server;
+
+    #[tokio::test]
+    async fn retries_once_with_renewed_session_id_then_succeeds() {
+        let mut server = Server::new_async().await;
+        let first = server.mock("POST", "/")
+            .with_status(409)
+            .with_header("x-transmission-session-id", "fresh-id")
+            .create_async()
+            .await;
+        let second = server.mock("POST", "/")
+            .match_header("x-transmission-session-id", "fresh-id")
+            .with_status(200)
+            .with_body(r#"{"result":"success","arguments":{}}"#)
+            .create_async()
+            .await;
+
+        let client = TransClient::new(&server.url());
+        let resp = client.call::<Nothing>(RpcRequest::session_get()).await;
+
+        assert!(resp.is_ok());
+        first.assert_async().await;
+        second.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn surfaces_an_error_when_the_retry_also_gets_a_409() {
+        let mut server = Server::new_async().await;
+        let mock = server.mock("POST", "/")
+            .with_status(409)
+            .with_header("x-transmission-session-id", "fresh-id")
+            .expect(2)
+            .create_async()
+            .await;
+
+        let client = TransClient::new(&server.url());
+        let resp = client.call::<Nothing>(RpcRequest::session_get()).await;
+
+        assert!(resp.is_err());
+        mock.assert_async().await;
+    }
+}
+
+#[cfg(test)]
+mod torrent_set_tests {
+    use crate::{TransClient, TorrentSetArgs};
+
+    #[tokio::test]
+    async fn rejects_an_empty_ids_list_without_making_a_request() {
+        // Deliberately an unroutable address: if this test ever starts
+        // failing because of a connection error instead of the intended
+        // validation error, that's a sign the empty-ids guard regressed.
+        let client = TransClient::new("http://127.0.0.1:0");
+        let args = TorrentSetArgs {
+            ids: vec![],
+            ..Default::default()
+        };
+
+        let error = client.torrent_set(args).await.expect_err("empty ids should be rejected");
+
+        assert_eq!(error.to_string(), "torrent_set requires at least one torrent id");
+    }
+}
+
+#[cfg(test)]
+mod with_config_tests {
+    use crate::TransClient;
+    use std::time::Duration;
+
+    #[test]
+    fn builds_a_client_with_the_given_url_and_options() {
+        let client = TransClient::with_config(
+            "http://127.0.0.1:9091/transmission/rpc",
+            None,
+            true,
+            Some(Duration::from_secs(5)),
+            Some("transmission-rpc-test/1.0")
+        ).expect("with_config should build a client from valid options");
+
+        assert_eq!(client.url, "http://127.0.0.1:9091/transmission/rpc");
+    }
+}
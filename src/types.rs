@@ -0,0 +1,309 @@
+use serde::{Deserialize, Serialize};
+
+error_chain! {
+    foreign_links {
+        Reqwest(reqwest::Error);
+        Utf8(std::str::Utf8Error);
+        Var(std::env::VarError);
+        Json(serde_json::Error);
+        Header(reqwest::header::ToStrError);
+    }
+}
+
+/// Basic HTTP authentication credentials
+#[derive(Debug, Clone)]
+pub struct BasicAuth {
+    pub user: String,
+    pub password: String
+}
+
+/// Marker trait implemented by every `arguments` payload returned
+/// inside an `RpcResponse`
+pub trait RpcResponseArgument {}
+
+/// Generic envelope returned by every Transmission RPC call
+#[derive(Deserialize, Debug)]
+pub struct RpcResponse<T: RpcResponseArgument> {
+    pub arguments: T,
+    pub result: String
+}
+
+/// Empty arguments payload for calls that return nothing useful
+#[derive(Deserialize, Debug)]
+pub struct Nothing {}
+impl RpcResponseArgument for Nothing {}
+
+/// Arguments payload for `session-get`
+#[derive(Deserialize, Debug)]
+pub struct SessionGet {
+    pub version: Option<String>,
+    #[serde(rename = "rpc-version")]
+    pub rpc_version: Option<i32>,
+    #[serde(rename = "download-dir")]
+    pub download_dir: Option<String>,
+    #[serde(rename = "incomplete-dir")]
+    pub incomplete_dir: Option<String>,
+    #[serde(rename = "incomplete-dir-enabled")]
+    pub incomplete_dir_enabled: Option<bool>,
+    #[serde(rename = "speed-limit-down")]
+    pub speed_limit_down: Option<i64>,
+    #[serde(rename = "speed-limit-down-enabled")]
+    pub speed_limit_down_enabled: Option<bool>,
+    #[serde(rename = "speed-limit-up")]
+    pub speed_limit_up: Option<i64>,
+    #[serde(rename = "speed-limit-up-enabled")]
+    pub speed_limit_up_enabled: Option<bool>,
+    #[serde(rename = "peer-limit-global")]
+    pub peer_limit_global: Option<i64>
+}
+impl RpcResponseArgument for SessionGet {}
+
+/// Partial set of mutable session settings for `session-set`; every
+/// field is optional and omitted from the request when `None`
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SessionSet {
+    #[serde(rename = "download-dir", skip_serializing_if = "Option::is_none")]
+    pub download_dir: Option<String>,
+    #[serde(rename = "incomplete-dir", skip_serializing_if = "Option::is_none")]
+    pub incomplete_dir: Option<String>,
+    #[serde(rename = "incomplete-dir-enabled", skip_serializing_if = "Option::is_none")]
+    pub incomplete_dir_enabled: Option<bool>,
+    #[serde(rename = "speed-limit-down", skip_serializing_if = "Option::is_none")]
+    pub speed_limit_down: Option<i64>,
+    #[serde(rename = "speed-limit-down-enabled", skip_serializing_if = "Option::is_none")]
+    pub speed_limit_down_enabled: Option<bool>,
+    #[serde(rename = "speed-limit-up", skip_serializing_if = "Option::is_none")]
+    pub speed_limit_up: Option<i64>,
+    #[serde(rename = "speed-limit-up-enabled", skip_serializing_if = "Option::is_none")]
+    pub speed_limit_up_enabled: Option<bool>,
+    #[serde(rename = "peer-limit-global", skip_serializing_if = "Option::is_none")]
+    pub peer_limit_global: Option<i64>
+}
+
+/// Arguments payload for `torrent-get`
+#[derive(Deserialize, Debug)]
+pub struct Torrents<T> {
+    pub torrents: Vec<T>
+}
+impl<T> RpcResponseArgument for Torrents<T> {}
+
+/// A single torrent as returned by `torrent-get`
+#[derive(Deserialize, Debug)]
+pub struct Torrent {
+    pub id: Option<i64>,
+    pub name: Option<String>,
+    pub files: Option<Vec<TorrentFile>>,
+    #[serde(rename = "fileStats")]
+    pub file_stats: Option<Vec<TorrentFileStat>>,
+    pub priorities: Option<Vec<i64>>,
+    pub wanted: Option<Vec<bool>>
+}
+
+/// A single file inside a torrent, as returned by the `files` field
+#[derive(Deserialize, Debug)]
+pub struct TorrentFile {
+    pub name: Option<String>,
+    pub length: Option<i64>,
+    #[serde(rename = "bytesCompleted")]
+    pub bytes_completed: Option<i64>
+}
+
+/// Per-file download state, as returned by the `fileStats` field
+#[derive(Deserialize, Debug)]
+pub struct TorrentFileStat {
+    #[serde(rename = "bytesCompleted")]
+    pub bytes_completed: Option<i64>,
+    pub wanted: Option<bool>,
+    pub priority: Option<i64>
+}
+
+/// Fields that can be requested from `torrent-get`
+#[derive(Serialize, Debug, Clone)]
+pub enum TorrentGetField {
+    #[serde(rename = "id")]
+    Id,
+    #[serde(rename = "name")]
+    Name,
+    #[serde(rename = "files")]
+    Files,
+    #[serde(rename = "fileStats")]
+    FileStats,
+    #[serde(rename = "priorities")]
+    Priorities,
+    #[serde(rename = "wanted")]
+    Wanted
+}
+
+/// Actions supported by `torrent-start`/`torrent-stop`/etc.
+#[derive(Serialize, Debug, Clone)]
+pub enum TorrentAction {
+    #[serde(rename = "torrent-start")]
+    Start,
+    #[serde(rename = "torrent-start-now")]
+    StartNow,
+    #[serde(rename = "torrent-stop")]
+    Stop,
+    #[serde(rename = "torrent-verify")]
+    Verify,
+    #[serde(rename = "torrent-reannounce")]
+    Reannounce
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TorrentGetArgs {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<TorrentGetField>>
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TorrentActionArgs {
+    pub ids: Vec<i64>
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TorrentRemoveArgs {
+    pub ids: Vec<i64>,
+    #[serde(rename = "delete-local-data")]
+    pub delete_local_data: bool
+}
+
+/// Arguments for `torrent-set`; only `ids` is required, everything
+/// else is left out of the request when `None`
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct TorrentSetArgs {
+    pub ids: Vec<i64>,
+    #[serde(rename = "files-wanted", skip_serializing_if = "Option::is_none")]
+    pub files_wanted: Option<Vec<i64>>,
+    #[serde(rename = "files-unwanted", skip_serializing_if = "Option::is_none")]
+    pub files_unwanted: Option<Vec<i64>>,
+    #[serde(rename = "priority-high", skip_serializing_if = "Option::is_none")]
+    pub priority_high: Option<Vec<i64>>,
+    #[serde(rename = "priority-normal", skip_serializing_if = "Option::is_none")]
+    pub priority_normal: Option<Vec<i64>>,
+    #[serde(rename = "priority-low", skip_serializing_if = "Option::is_none")]
+    pub priority_low: Option<Vec<i64>>
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Args {
+    TorrentGet(TorrentGetArgs),
+    TorrentAction(TorrentActionArgs),
+    TorrentRemove(TorrentRemoveArgs),
+    TorrentSet(TorrentSetArgs),
+    SessionSet(SessionSet)
+}
+
+/// A single JSON-RPC request sent to the Transmission daemon
+#[derive(Serialize, Debug, Clone)]
+pub struct RpcRequest {
+    method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arguments: Option<Args>
+}
+
+impl RpcRequest {
+    pub fn session_get() -> RpcRequest {
+        RpcRequest {
+            method: "session-get".to_string(),
+            arguments: None
+        }
+    }
+
+    pub fn torrent_get(fields: Vec<TorrentGetField>) -> RpcRequest {
+        RpcRequest {
+            method: "torrent-get".to_string(),
+            arguments: Some(Args::TorrentGet(TorrentGetArgs { fields: Some(fields) }))
+        }
+    }
+
+    pub fn torrent_action(action: TorrentAction, ids: Vec<i64>) -> RpcRequest {
+        let method = match action {
+            TorrentAction::Start => "torrent-start",
+            TorrentAction::StartNow => "torrent-start-now",
+            TorrentAction::Stop => "torrent-stop",
+            TorrentAction::Verify => "torrent-verify",
+            TorrentAction::Reannounce => "torrent-reannounce"
+        };
+        RpcRequest {
+            method: method.to_string(),
+            arguments: Some(Args::TorrentAction(TorrentActionArgs { ids }))
+        }
+    }
+
+    pub fn torrent_remove(ids: Vec<i64>, delete_local_data: bool) -> RpcRequest {
+        RpcRequest {
+            method: "torrent-remove".to_string(),
+            arguments: Some(Args::TorrentRemove(TorrentRemoveArgs { ids, delete_local_data }))
+        }
+    }
+
+    pub fn torrent_set(args: TorrentSetArgs) -> RpcRequest {
+        RpcRequest {
+            method: "torrent-set".to_string(),
+            arguments: Some(Args::TorrentSet(args))
+        }
+    }
+
+    pub fn session_set(args: SessionSet) -> RpcRequest {
+        RpcRequest {
+            method: "session-set".to_string(),
+            arguments: Some(Args::SessionSet(args))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn torrent_remove_serializes_ids_and_delete_local_data() {
+        let request = RpcRequest::torrent_remove(vec![1, 2], true);
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value, json!({
+            "method": "torrent-remove",
+            "arguments": {
+                "ids": [1, 2],
+                "delete-local-data": true
+            }
+        }));
+    }
+
+    #[test]
+    fn torrent_set_only_serializes_fields_that_were_set() {
+        let args = TorrentSetArgs {
+            ids: vec![7],
+            priority_high: Some(vec![0, 1]),
+            ..Default::default()
+        };
+        let request = RpcRequest::torrent_set(args);
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value, json!({
+            "method": "torrent-set",
+            "arguments": {
+                "ids": [7],
+                "priority-high": [0, 1]
+            }
+        }));
+    }
+
+    #[test]
+    fn session_set_only_serializes_fields_that_were_set() {
+        let args = SessionSet {
+            download_dir: Some("/downloads".to_string()),
+            speed_limit_down: Some(500),
+            ..Default::default()
+        };
+        let request = RpcRequest::session_set(args);
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value, json!({
+            "method": "session-set",
+            "arguments": {
+                "download-dir": "/downloads",
+                "speed-limit-down": 500
+            }
+        }));
+    }
+}